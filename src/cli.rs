@@ -0,0 +1,124 @@
+//! Command-line arguments and the resolved `Config` derived from them.
+
+use clap::Parser;
+
+use crate::graph::{FocusDirection, FocusTarget};
+
+/// Raw CLI arguments, parsed with `clap`. Kept separate from `Config` so the
+/// graph-building code never has to think about argument syntax (e.g.
+/// `--focus`/`--invert` being mutually exclusive ways of building the same
+/// `Option<FocusTarget>`).
+#[derive(Debug, Parser)]
+#[command(name = "cargo-depgraph", about = "Visualize a workspace's dependency graph")]
+pub struct Args {
+    /// Include normal dependency edges.
+    #[arg(long = "no-normal-deps", action = clap::ArgAction::SetFalse)]
+    pub normal_deps: bool,
+
+    /// Include build-dependency edges.
+    #[arg(long = "no-build-deps", action = clap::ArgAction::SetFalse)]
+    pub build_deps: bool,
+
+    /// Include dev-dependency edges.
+    #[arg(long = "no-dev-deps", action = clap::ArgAction::SetFalse)]
+    pub dev_deps: bool,
+
+    /// Include target-specific (`cfg(...)`-gated) dependency edges.
+    #[arg(long = "no-target-deps", action = clap::ArgAction::SetFalse)]
+    pub target_deps: bool,
+
+    /// Emit a node for each package's enabled features, and route the
+    /// dependency edges they gate through that node instead of straight
+    /// from the package, so feature-driven bloat can be traced back to the
+    /// feature that pulled it in.
+    #[arg(long)]
+    pub features: bool,
+
+    /// Restrict the graph to the paths from workspace members down into
+    /// this package: "what makes this end up in my build".
+    #[arg(long, conflicts_with = "invert")]
+    pub focus: Option<String>,
+
+    /// Restrict the graph to the paths from this package down into its own
+    /// dependencies: the inverse of `--focus`.
+    #[arg(long, conflicts_with = "focus")]
+    pub invert: Option<String>,
+
+    /// Print a note for every dependency cycle that's only closed by a
+    /// dev-dependency edge (harmless; cargo builds dev-deps separately). A
+    /// cycle that exists without any dev-dependency edge is always a hard
+    /// error, with or without this flag.
+    #[arg(long)]
+    pub detect_cycles: bool,
+
+    /// Annotate package nodes with their local cargo-crev review status
+    /// (green/red/gray fill for positively-reviewed/flagged/unreviewed).
+    /// Degrades to plain nodes if no crev setup is found.
+    #[arg(long)]
+    pub crev: bool,
+}
+
+impl Args {
+    /// Turns the raw, syntax-level `Args` into the `Config` the graph
+    /// builder actually consumes.
+    pub fn into_config(self) -> anyhow::Result<Config> {
+        let focus = match (self.focus, self.invert) {
+            (Some(package), None) => {
+                Some(FocusTarget { package, direction: FocusDirection::Forward })
+            }
+            (None, Some(package)) => {
+                Some(FocusTarget { package, direction: FocusDirection::Reverse })
+            }
+            (None, None) => None,
+            // clap's `conflicts_with` already rejects this at parse time;
+            // kept as a defensive check for callers that build `Args` by
+            // hand (e.g. tests) instead of through `Args::parse()`.
+            (Some(_), Some(_)) => anyhow::bail!("--focus and --invert can't be used together"),
+        };
+
+        Ok(Config {
+            normal_deps: self.normal_deps,
+            build_deps: self.build_deps,
+            dev_deps: self.dev_deps,
+            target_deps: self.target_deps,
+            features: self.features,
+            focus,
+            detect_cycles: self.detect_cycles,
+            crev: self.crev,
+        })
+    }
+}
+
+/// Resolved configuration controlling how the dependency graph is built and
+/// rendered. Unlike `Args`, every field here is already in the shape the
+/// graph-building code wants it (e.g. `focus` is a single `Option`, not two
+/// conflicting strings).
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub normal_deps: bool,
+    pub build_deps: bool,
+    pub dev_deps: bool,
+    pub target_deps: bool,
+    pub features: bool,
+    pub focus: Option<FocusTarget>,
+    pub detect_cycles: bool,
+    pub crev: bool,
+}
+
+impl Default for Config {
+    /// Mirrors `Args`' own defaults: every dependency kind is included
+    /// unless explicitly excluded, while the opt-in features (feature
+    /// nodes, `--focus`/`--invert`) start off.
+    fn default() -> Self {
+        Self {
+            normal_deps: true,
+            build_deps: true,
+            dev_deps: true,
+            target_deps: true,
+            features: false,
+            focus: None,
+            detect_cycles: false,
+            crev: false,
+        }
+    }
+}
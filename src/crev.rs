@@ -0,0 +1,235 @@
+//! A thin, read-only view over the local `cargo-crev` proof database, used
+//! to annotate graph nodes with dependency-review status.
+//!
+//! This intentionally doesn't depend on `crev-lib`/`crev-data`: it just
+//! walks the locally-trusted proof files crev already maintains under the
+//! user's crev config directory and pulls out the bits we need (package
+//! identity and review rating). If no crev setup is found, callers get
+//! `None` back and render the graph exactly as before.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The review status crev has on record for a specific package version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewStatus {
+    /// At least one trusted review rates this version positively (or
+    /// higher) and none flag it.
+    Positive,
+    /// At least one trusted review flags this version as negative/dangerous.
+    Flagged,
+    /// No review on record for this version, or only reviews whose rating
+    /// is neither positive nor negative (e.g. `neutral`).
+    Unreviewed,
+}
+
+/// An in-memory index of `(package name, version) -> ReviewStatus`, built
+/// once from the local proof database.
+#[derive(Debug, Default)]
+pub struct CrevDb {
+    ratings: HashMap<(String, String), ReviewStatus>,
+}
+
+impl CrevDb {
+    /// Loads the local crev proof database, if one is set up. Returns
+    /// `Ok(None)` (not an error) when crev simply isn't configured, so
+    /// `--crev` degrades gracefully instead of failing the whole run.
+    pub fn open() -> anyhow::Result<Option<Self>> {
+        let Some(proofs_dir) = proofs_dir() else {
+            return Ok(None);
+        };
+        if !proofs_dir.is_dir() {
+            return Ok(None);
+        }
+
+        let mut db = Self::default();
+        for entry in walk_proof_files(&proofs_dir)? {
+            for (id, status) in parse_proofs(&fs::read_to_string(&entry)?) {
+                // Whatever order the proofs come in, a flagged rating for the
+                // same version wins over everything, and a positive one over
+                // a neutral one, mirroring crev's own "flagged beats
+                // positive" trust rule.
+                db.ratings
+                    .entry(id)
+                    .and_modify(|existing| match status {
+                        ReviewStatus::Flagged => *existing = ReviewStatus::Flagged,
+                        ReviewStatus::Positive if *existing == ReviewStatus::Unreviewed => {
+                            *existing = ReviewStatus::Positive;
+                        }
+                        _ => {}
+                    })
+                    .or_insert(status);
+            }
+        }
+
+        Ok(Some(db))
+    }
+
+    /// Looks up the review status for `name`/`version`, defaulting to
+    /// `Unreviewed` when crev has no proof on file for it.
+    pub fn status_for(&self, name: &str, version: &str) -> ReviewStatus {
+        self.ratings
+            .get(&(name.to_owned(), version.to_owned()))
+            .copied()
+            .unwrap_or(ReviewStatus::Unreviewed)
+    }
+}
+
+fn proofs_dir() -> Option<PathBuf> {
+    Some(dirs_config_dir()?.join("crev").join("proofs"))
+}
+
+fn dirs_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+fn walk_proof_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(walk_proof_files(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "proof") {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// A crev proof *file* is an append-only log: a `crev review` (or a trust
+/// proof, certification, etc.) just gets appended to the existing file
+/// rather than creating a new one, so a single file routinely holds many
+/// package-review envelopes accumulated over time. Splits `contents` on the
+/// `-----BEGIN/END CREV PACKAGE REVIEW-----` envelope markers and parses
+/// each one independently, so none of them get silently dropped.
+fn parse_proofs(contents: &str) -> Vec<((String, String), ReviewStatus)> {
+    contents
+        .split("-----BEGIN CREV PACKAGE REVIEW-----")
+        .skip(1)
+        .filter_map(|rest| rest.split("-----END CREV PACKAGE REVIEW-----").next())
+        .filter_map(parse_proof)
+        .collect()
+}
+
+/// Pulls `package.name`, `package.version` and `review.rating` out of a
+/// single crev proof document (the body between one pair of `BEGIN`/`END`
+/// envelope markers) using a section-aware line scan. crev proofs are YAML,
+/// but we only need three scalar fields nested under two particular
+/// top-level keys, so a full parser dependency isn't worth pulling in just
+/// for this.
+///
+/// A proof also has a `from:` section (the reviewer's own identity, with
+/// its own `name:`) and a top-level `version:` (the document schema
+/// version, usually `-1`), so `name:`/`version:` can't just be matched
+/// anywhere — only while inside the `package:` section. Likewise `rating:`
+/// is only meaningful inside `review:`.
+fn parse_proof(contents: &str) -> Option<((String, String), ReviewStatus)> {
+    let mut name = None;
+    let mut version = None;
+    let mut rating = None;
+    let mut section = "";
+
+    for line in contents.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Top-level keys (`package:`, `review:`, `from:`, ...) start at
+        // column 0; everything indented under them belongs to that
+        // section, crev proofs being two-space-indented YAML.
+        if !line.starts_with(' ') {
+            section = line.trim_end_matches(':');
+            continue;
+        }
+
+        let line = line.trim();
+        if section == "package" {
+            if let Some(value) = line.strip_prefix("name:") {
+                name = Some(value.trim().trim_matches('"').to_owned());
+            } else if let Some(value) = line.strip_prefix("version:") {
+                version = Some(value.trim().trim_matches('"').to_owned());
+            }
+        } else if section == "review" {
+            if let Some(value) = line.strip_prefix("rating:") {
+                rating = Some(value.trim().trim_matches('"').to_owned());
+            }
+        }
+    }
+
+    let status = match rating?.as_str() {
+        "strong" | "positive" => ReviewStatus::Positive,
+        "negative" | "dangerous" => ReviewStatus::Flagged,
+        _ => ReviewStatus::Unreviewed,
+    };
+
+    Some(((name?, version?), status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A realistic crev `package review` proof: note the top-level
+    /// `version: -1` (schema version) and `from.name` (reviewer identity),
+    /// both of which used to collide with `package.name`/`package.version`
+    /// in the old flat line scan.
+    const PROOF: &str = "\
+-----BEGIN CREV PACKAGE REVIEW-----
+version: -1
+date: \"2024-01-01T00:00:00Z\"
+from:
+  id-type: crev
+  id: abc123
+  name: \"Some Reviewer\"
+package:
+  source: \"https://crates.io\"
+  name: \"serde\"
+  version: \"1.0.0\"
+  digest: deadbeef
+review:
+  thoroughness: medium
+  understanding: medium
+  rating: positive
+comment: \"looks fine\"
+-----END CREV PACKAGE REVIEW-----
+";
+
+    #[test]
+    fn parses_name_and_version_from_the_package_section_only() {
+        let ((name, version), status) = parse_proof(PROOF).expect("proof should parse");
+        assert_eq!(name, "serde");
+        assert_eq!(version, "1.0.0");
+        assert_eq!(status, ReviewStatus::Positive);
+    }
+
+    #[test]
+    fn missing_rating_yields_no_result() {
+        let proof = "package:\n  name: \"serde\"\n  version: \"1.0.0\"\n";
+        assert!(parse_proof(proof).is_none());
+    }
+
+    /// crev's local proof store is an append-only log: a single `.proof`
+    /// file routinely accumulates several package-review envelopes over
+    /// time, one per review ever made. `parse_proofs` must return all of
+    /// them, not just the first.
+    #[test]
+    fn a_single_file_with_two_proofs_yields_both_ratings() {
+        let second = PROOF.replace("serde", "widgets").replace("positive", "negative");
+        let log = format!("{PROOF}{second}");
+
+        let proofs = parse_proofs(&log);
+        assert_eq!(proofs.len(), 2);
+
+        let serde = proofs.iter().find(|((name, _), _)| name == "serde").expect("serde proof");
+        assert_eq!(serde.1, ReviewStatus::Positive);
+        let widgets = proofs.iter().find(|((name, _), _)| name == "widgets").expect("widgets proof");
+        assert_eq!(widgets.1, ReviewStatus::Flagged);
+    }
+}
@@ -0,0 +1,129 @@
+use cargo_metadata::DependencyKind as MetaDepKind;
+
+/// Which kind(s) of dependency edge led to this node, as a bitset: a crate
+/// that's both a normal dependency of one workspace member and a build-dep
+/// of a dev-dep of another keeps both bits set, rather than collapsing to
+/// whichever one happened to be folded in last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepKind(u8);
+
+impl DepKind {
+    pub const NORMAL: DepKind = DepKind(1 << 0);
+    pub const BUILD: DepKind = DepKind(1 << 1);
+    pub const DEV: DepKind = DepKind(1 << 2);
+
+    /// A path is "dev" only if every edge along it is a dev-dependency edge;
+    /// a single normal/build edge elsewhere in the graph means the crate
+    /// still ends up in a real build, dev-dependency or not.
+    pub fn is_dev(self) -> bool {
+        self.0 != 0 && self.0 == Self::DEV.0
+    }
+
+    /// Folds an incoming edge's kind into this one. A node reached two
+    /// different ways keeps every kind it was reached by, so a dev-only
+    /// path can't hide the fact that the same crate is also a normal/build
+    /// dependency elsewhere.
+    pub fn combine_incoming(&mut self, other: DepKind) {
+        self.0 |= other.0;
+    }
+
+    /// Extends this edge's own declared kind with `node_kind`, the kind
+    /// reaching the node it leaves, so an edge ends up tagged with every kind along the
+    /// path leading up to it (e.g. "build-dep of a dev-dep" keeps both
+    /// `BUILD` and `DEV` set, distinct from a plain `DEV` or `BUILD` edge).
+    pub fn update_outgoing(&mut self, node_kind: DepKind) {
+        self.0 |= node_kind.0;
+    }
+
+    /// A short label for rendering this kind on an edge, e.g. in Graphviz
+    /// output. Composite kinds are joined with `+`, in normal/build/dev
+    /// order.
+    pub fn label(self) -> String {
+        let mut parts = Vec::new();
+        if self.has_normal() {
+            parts.push("normal");
+        }
+        if self.has_build() {
+            parts.push("build");
+        }
+        if self.has_dev() {
+            parts.push("dev");
+        }
+
+        parts.join("+")
+    }
+
+    pub fn has_normal(self) -> bool {
+        self.0 & Self::NORMAL.0 != 0
+    }
+
+    pub fn has_build(self) -> bool {
+        self.0 & Self::BUILD.0 != 0
+    }
+
+    pub fn has_dev(self) -> bool {
+        self.0 & Self::DEV.0 != 0
+    }
+}
+
+impl From<MetaDepKind> for DepKind {
+    fn from(kind: MetaDepKind) -> Self {
+        match kind {
+            MetaDepKind::Normal => DepKind::NORMAL,
+            MetaDepKind::Build => DepKind::BUILD,
+            MetaDepKind::Development => DepKind::DEV,
+            _ => DepKind::NORMAL,
+        }
+    }
+}
+
+/// Information about a single dependency edge, accumulated as
+/// `update_dep_info` propagates it through the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepInfo {
+    pub kind: DepKind,
+    pub is_target_dep: bool,
+    pub visited: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composite_kind_keeps_every_bit_it_was_reached_by() {
+        let mut kind = DepKind::DEV;
+        kind.update_outgoing(DepKind::BUILD);
+
+        assert!(!kind.is_dev());
+        assert_eq!(kind.label(), "build+dev");
+    }
+
+    #[test]
+    fn plain_dev_path_is_still_reported_as_dev() {
+        let mut kind = DepKind::DEV;
+        kind.combine_incoming(DepKind::DEV);
+
+        assert!(kind.is_dev());
+        assert_eq!(kind.label(), "dev");
+    }
+
+    #[test]
+    fn a_single_normal_edge_anywhere_rules_out_dev_only() {
+        let mut kind = DepKind::DEV;
+        kind.combine_incoming(DepKind::NORMAL);
+
+        assert!(!kind.is_dev());
+        assert_eq!(kind.label(), "normal+dev");
+    }
+
+    #[test]
+    fn composite_kind_exposes_every_bit_it_carries() {
+        let mut kind = DepKind::BUILD;
+        kind.combine_incoming(DepKind::DEV);
+
+        assert!(!kind.has_normal());
+        assert!(kind.has_build());
+        assert!(kind.has_dev());
+    }
+}
@@ -0,0 +1,198 @@
+//! Renders a `DepGraph` as GraphViz `dot` source.
+
+use std::fmt::Write as _;
+
+use petgraph::visit::EdgeRef;
+
+use crate::{
+    cli::Config,
+    crev::ReviewStatus,
+    dep_info::DepKind,
+    graph::{DepGraph, Edge, Node},
+    package::Package,
+};
+
+/// Renders `graph` as a complete `dot` document (`digraph { ... }`), ready
+/// to be piped into `dot -Tsvg` or similar. `config`'s per-dependency-kind
+/// flags (`--no-normal-deps` etc.) filter which composite edge kinds are
+/// drawn, same as they filter which edges get added to the graph in the
+/// first place.
+pub fn render_dot(graph: &DepGraph, config: &Config) -> String {
+    let mut out = String::from("digraph dependencies {\n");
+
+    for idx in graph.node_indices() {
+        let attrs = match &graph[idx] {
+            Node::Package(pkg) => package_attrs(pkg),
+            // Feature nodes get their own shape/color so feature-driven
+            // bloat can be traced back to the feature that pulled it in,
+            // instead of blending in with regular package nodes.
+            Node::Feature { name, .. } => {
+                format!("label=\"{name}\", shape=hexagon, style=filled, fillcolor=lightyellow")
+            }
+        };
+        writeln!(out, "    {} [{attrs}];", idx.index()).unwrap();
+    }
+
+    for edge in graph.edge_references() {
+        let attrs = match edge.weight() {
+            Edge::Dep(info) if should_render_kind(config, info.kind) => dep_edge_attrs(info.kind),
+            Edge::Dep(_) => continue,
+            // Feature-activation edges aren't a dependency relationship, so
+            // they're drawn dashed to stay visually distinct from them.
+            Edge::Feature => "style=dashed".to_owned(),
+        };
+        writeln!(out, "    {} -> {} [{attrs}];", edge.source().index(), edge.target().index())
+            .unwrap();
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn package_attrs(pkg: &Package) -> String {
+    let Some(status) = pkg.review_status else {
+        return format!("label=\"{}\", shape=box", pkg.name());
+    };
+
+    // `--crev` fills a package node to reflect the local trust database:
+    // green for a trusted review, red for a flagged one, gray for no review
+    // on record at all, so an untrustworthy or unreviewed dependency stands
+    // out in the rendered graph without having to cross-reference `crev`
+    // output by hand.
+    let fillcolor = match status {
+        ReviewStatus::Positive => "palegreen",
+        ReviewStatus::Flagged => "lightcoral",
+        ReviewStatus::Unreviewed => "lightgray",
+    };
+    format!("label=\"{}\", shape=box, style=filled, fillcolor={fillcolor}", pkg.name())
+}
+
+/// Whether an edge carrying composite `kind` should be drawn at all, given
+/// `config`'s per-kind filters. A composite edge (e.g. "build-of-dev") is
+/// kept as long as at least one of the kinds it carries is enabled, so
+/// excluding dev-deps doesn't also hide a path that's *also* a real
+/// build-dependency.
+fn should_render_kind(config: &Config, kind: DepKind) -> bool {
+    (config.normal_deps && kind.has_normal())
+        || (config.build_deps && kind.has_build())
+        || (config.dev_deps && kind.has_dev())
+}
+
+/// A composite kind gets its own color, distinct from any single kind, so
+/// e.g. "build-of-dev" can be told apart from a plain "build" or "dev" edge
+/// at a glance instead of only in the label text.
+fn dep_edge_attrs(kind: DepKind) -> String {
+    let color = match (kind.has_normal(), kind.has_build(), kind.has_dev()) {
+        (true, false, false) => "black",
+        (false, true, false) => "blue",
+        (false, false, true) => "darkorange",
+        _ => "crimson",
+    };
+    format!("label=\"{}\", color={color}", kind.label())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep_info::DepInfo;
+
+    fn pkg_node(name: &str) -> Node {
+        pkg_node_with_review(name, None)
+    }
+
+    fn pkg_node_with_review(name: &str, review_status: Option<ReviewStatus>) -> Node {
+        Node::Package(Package::new(
+            serde_json::from_value(serde_json::json!({
+                "name": name,
+                "version": "0.1.0",
+                "id": format!("{name} 0.1.0 (path+file:///tmp/{name})"),
+                "license": null,
+                "license_file": null,
+                "description": null,
+                "source": null,
+                "dependencies": [],
+                "targets": [],
+                "features": {},
+                "manifest_path": format!("/tmp/{name}/Cargo.toml"),
+                "edition": "2021",
+                "authors": [],
+                "metadata": null,
+            }))
+            .unwrap(),
+            false,
+            review_status,
+        ))
+    }
+
+    #[test]
+    fn feature_nodes_render_with_a_distinct_shape() {
+        let mut graph = DepGraph::new();
+        let pkg = graph.add_node(pkg_node("a"));
+        let feature = graph.add_node(Node::Feature {
+            package: pkg_node("a").as_package().unwrap().meta.id.clone(),
+            name: "fancy".to_owned(),
+        });
+        graph.add_edge(pkg, feature, Edge::Feature);
+
+        let dot = render_dot(&graph, &Config::default());
+        assert!(dot.contains("shape=hexagon"));
+        assert!(dot.contains("style=dashed"));
+    }
+
+    #[test]
+    fn composite_edges_get_their_own_color_distinct_from_a_plain_kind() {
+        let mut graph = DepGraph::new();
+        let a = graph.add_node(pkg_node("a"));
+        let b = graph.add_node(pkg_node("b"));
+        let c = graph.add_node(pkg_node("c"));
+        graph.add_edge(
+            a,
+            b,
+            Edge::Dep(DepInfo { kind: DepKind::NORMAL, is_target_dep: false, visited: false }),
+        );
+        let mut composite = DepKind::BUILD;
+        composite.combine_incoming(DepKind::DEV);
+        graph.add_edge(
+            b,
+            c,
+            Edge::Dep(DepInfo { kind: composite, is_target_dep: false, visited: false }),
+        );
+
+        let dot = render_dot(&graph, &Config::default());
+        assert!(dot.contains("color=black"));
+        assert!(dot.contains("color=crimson"));
+    }
+
+    #[test]
+    fn disabling_a_dep_kind_hides_edges_that_are_only_ever_that_kind() {
+        let mut graph = DepGraph::new();
+        let a = graph.add_node(pkg_node("a"));
+        let b = graph.add_node(pkg_node("b"));
+        graph.add_edge(
+            a,
+            b,
+            Edge::Dep(DepInfo { kind: DepKind::DEV, is_target_dep: false, visited: false }),
+        );
+
+        let config = Config { dev_deps: false, ..Config::default() };
+        let dot = render_dot(&graph, &config);
+        assert!(!dot.contains("->"));
+    }
+
+    #[test]
+    fn crev_review_status_fills_package_nodes_by_trust_level() {
+        let mut graph = DepGraph::new();
+        graph.add_node(pkg_node_with_review("trusted", Some(ReviewStatus::Positive)));
+        graph.add_node(pkg_node_with_review("bad", Some(ReviewStatus::Flagged)));
+        graph.add_node(pkg_node_with_review("unknown", Some(ReviewStatus::Unreviewed)));
+        graph.add_node(pkg_node("no-crev-data"));
+
+        let dot = render_dot(&graph, &Config::default());
+        assert!(dot.contains("fillcolor=palegreen"));
+        assert!(dot.contains("fillcolor=lightcoral"));
+        assert!(dot.contains("fillcolor=lightgray"));
+        // A node with no crev lookup at all (e.g. `--crev` wasn't passed)
+        // renders as a plain box, same as before this feature existed.
+        assert!(dot.contains("label=\"no-crev-data\", shape=box]"));
+    }
+}
@@ -1,25 +1,250 @@
-use std::collections::{hash_map::Entry as HashMapEntry, HashMap, VecDeque};
+use std::collections::{hash_map::Entry as HashMapEntry, BTreeSet, HashMap, HashSet, VecDeque};
 
 use anyhow::Context;
 use cargo_metadata::{
-    DependencyKind as MetaDepKind, Metadata, Package as MetaPackage, PackageId, Resolve,
+    DependencyKind as MetaDepKind, Metadata, Node as MetaNode, Package as MetaPackage, PackageId,
+    Resolve,
 };
 use petgraph::{
-    algo::all_simple_paths,
+    algo::{all_simple_paths, tarjan_scc},
     graph::{DiGraph, NodeIndex},
+    visit::EdgeRef,
     Direction,
 };
 
-use crate::{cli::Config, dep_info::DepInfo, package::Package};
+use crate::{cli::Config, crev::CrevDb, dep_info::DepInfo, package::Package};
+
+/// A node in the dependency graph.
+///
+/// Most nodes are resolved packages, but when `Config::features` is enabled
+/// a package's activated features get their own nodes too, so the edges
+/// that are only pulled in behind a feature can be told apart from the
+/// package's unconditional dependencies.
+#[derive(Debug, Clone)]
+pub enum Node {
+    Package(Package),
+    Feature { package: PackageId, name: String },
+}
+
+impl Node {
+    pub fn as_package(&self) -> Option<&Package> {
+        match self {
+            Node::Package(pkg) => Some(pkg),
+            Node::Feature { .. } => None,
+        }
+    }
+}
+
+/// An edge in the dependency graph: either an actual package dependency, or
+/// the activation of one of a package's features.
+#[derive(Debug, Clone, Copy)]
+pub enum Edge {
+    Dep(DepInfo),
+    Feature,
+}
 
-pub type DepGraph = DiGraph<Package, DepInfo, u16>;
+pub type DepGraph = DiGraph<Node, Edge, u16>;
 
 pub fn get_dep_graph(metadata: Metadata, config: &Config) -> anyhow::Result<DepGraph> {
-    let mut builder = DepGraphBuilder::new(metadata)?;
+    let mut builder = DepGraphBuilder::new(metadata, config)?;
     builder.add_workspace_members()?;
     builder.add_dependencies(config)?;
 
-    Ok(builder.graph)
+    // Always guard against cycles before any DAG-assuming pass runs,
+    // regardless of whether the user asked to be told about dev-induced
+    // ones. This has to happen on the *full* graph, before `--focus`
+    // narrows it down: `focus_dep_graph` itself calls `update_dep_info`,
+    // which assumes a DAG just as much as `dedup_transitive_deps` does.
+    // Filtering can only ever remove nodes/edges, never add them, so a hard
+    // cycle in the focused subgraph would already have been a hard cycle
+    // in the full graph too.
+    let report = check_cycles(&builder.graph)?;
+
+    // Classify every edge's normal/build/dev/target kind only on the graph
+    // that's actually returned: `update_dep_info` folds each node's kind
+    // into its outgoing edges, so classifying the full graph before
+    // `--focus` filters it would leave bits from dropped edges behind on the
+    // surviving ones. `focus_dep_graph` classifies its filtered graph itself.
+    let graph = match &config.focus {
+        Some(focus) => focus_dep_graph(&builder.graph, &focus.package, focus.direction)?,
+        None => {
+            update_dep_info(&mut builder.graph);
+            builder.graph
+        }
+    };
+
+    if config.detect_cycles {
+        for cycle in &report.dev_induced {
+            eprintln!(
+                "note: dependency cycle closed by a dev-dependency, this is fine: {}",
+                cycle.packages.join(" -> ")
+            );
+        }
+    }
+
+    Ok(graph)
+}
+
+/// The packages making up a single strongly-connected component, in the
+/// order `petgraph::algo::tarjan_scc` returned them.
+#[derive(Debug, Clone)]
+pub struct Cycle {
+    pub packages: Vec<String>,
+}
+
+/// The result of a `--detect-cycles` pass: `hard` cycles exist even once
+/// dev-dependency edges are removed, meaning cargo itself couldn't build
+/// this graph; `dev_induced` cycles only close because of a dev-dependency
+/// edge, which is a legitimate (if surprising) thing for a crate to do.
+#[derive(Debug, Clone, Default)]
+pub struct CycleReport {
+    pub hard: Vec<Cycle>,
+    pub dev_induced: Vec<Cycle>,
+}
+
+/// Checks `graph` for cycles before any algorithm that assumes a DAG (e.g.
+/// `dedup_transitive_deps`, or the recursion in `update_node`) is allowed to
+/// run on it. Dev-dependencies are allowed to close a loop back to a crate
+/// that (transitively) depends on them, so only cycles that survive once
+/// dev-dependency edges are excluded are treated as a hard error.
+pub fn check_cycles(graph: &DepGraph) -> anyhow::Result<CycleReport> {
+    let hard = find_cycles(graph, true);
+    let all = find_cycles(graph, false);
+
+    // `hard` and `all` come from running `tarjan_scc` on two structurally
+    // different graphs (dev edges excluded vs. included), so even the same
+    // underlying cycle can come back with its packages in a different
+    // order. Compare as sets, not as `Vec`s, so that doesn't cause a hard
+    // cycle to also get reported a second time as "this is fine".
+    let hard_keys: HashSet<BTreeSet<&str>> =
+        hard.iter().map(|c| c.packages.iter().map(String::as_str).collect()).collect();
+    let dev_induced = all
+        .into_iter()
+        .filter(|c| !hard_keys.contains(&c.packages.iter().map(String::as_str).collect()))
+        .collect();
+
+    if !hard.is_empty() {
+        let mut msg =
+            String::from("found dependency cycle(s) that don't go through a dev-dependency:\n");
+        for cycle in &hard {
+            msg.push_str("  - ");
+            msg.push_str(&cycle.packages.join(" -> "));
+            msg.push('\n');
+        }
+        anyhow::bail!(msg);
+    }
+
+    Ok(CycleReport { hard, dev_induced })
+}
+
+fn find_cycles(graph: &DepGraph, exclude_dev: bool) -> Vec<Cycle> {
+    let reduced = graph.filter_map(
+        |_, node| Some(node.clone()),
+        |_, edge| match edge {
+            Edge::Dep(info) if exclude_dev && info.kind.is_dev() => None,
+            // Feature edges stay in: a dependency edge routed through a
+            // feature node only closes a cycle via the package -> feature
+            // edge leading up to it.
+            Edge::Dep(_) | Edge::Feature => Some(*edge),
+        },
+    );
+
+    tarjan_scc(&reduced)
+        .into_iter()
+        .filter(|scc| scc.len() > 1)
+        .map(|scc| Cycle {
+            packages: scc
+                .into_iter()
+                .filter_map(|idx| reduced[idx].as_package().map(|pkg| pkg.name().to_owned()))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Which direction to follow when focusing the graph on a single package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusDirection {
+    /// Keep only the paths running from workspace members down into the
+    /// focused package, i.e. "what makes this crate end up in my build".
+    Forward,
+    /// Keep only the paths running from the focused package down into its
+    /// own dependencies, i.e. the inverse of `Forward`.
+    Reverse,
+}
+
+/// A `--focus`/`--invert` request: the package to restrict the graph to,
+/// and which side of it to keep.
+#[derive(Debug, Clone)]
+pub struct FocusTarget {
+    pub package: String,
+    pub direction: FocusDirection,
+}
+
+/// Restricts `graph` to the subgraph of paths explaining why `package` is
+/// present: either the chains running from workspace members down into it
+/// (`FocusDirection::Forward`), or the chains running from it down into its
+/// own dependencies (`FocusDirection::Reverse`).
+fn focus_dep_graph(
+    graph: &DepGraph,
+    package: &str,
+    direction: FocusDirection,
+) -> anyhow::Result<DepGraph> {
+    let target_idx = find_package_node(graph, package)
+        .with_context(|| format!("package `{package}` not found in the dependency graph"))?;
+
+    let petgraph_dir = match direction {
+        FocusDirection::Forward => Direction::Incoming,
+        FocusDirection::Reverse => Direction::Outgoing,
+    };
+    let keep = reachable_closure(graph, target_idx, petgraph_dir);
+
+    let mut focused = graph.filter_map(
+        |idx, node| keep.contains(&idx).then(|| node.clone()),
+        |edge_idx, edge| {
+            let (src, dst) = graph.edge_endpoints(edge_idx).expect("edge to exist");
+            (keep.contains(&src) && keep.contains(&dst)).then_some(*edge)
+        },
+    );
+
+    // The filtered graph has a different set of edges reaching each node
+    // than the full graph did, so the normal/build/dev/target
+    // classification has to be recomputed. Edge kinds aren't reset here, so
+    // this is only a from-scratch classification when `graph` wasn't
+    // classified already (as in `get_dep_graph`).
+    for edge in focused.edge_weights_mut() {
+        if let Edge::Dep(info) = edge {
+            info.visited = false;
+        }
+    }
+    update_dep_info(&mut focused);
+
+    Ok(focused)
+}
+
+fn find_package_node(graph: &DepGraph, name: &str) -> Option<NodeIndex<u16>> {
+    graph
+        .node_indices()
+        .find(|&idx| matches!(&graph[idx], Node::Package(pkg) if pkg.name() == name))
+}
+
+/// BFS over `graph` from `start`, following edges in `direction`, collecting
+/// every node reached along the way (including `start` itself).
+fn reachable_closure(
+    graph: &DepGraph,
+    start: NodeIndex<u16>,
+    direction: Direction,
+) -> HashSet<NodeIndex<u16>> {
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(idx) = queue.pop_front() {
+        for neighbor in graph.neighbors_directed(idx, direction) {
+            if seen.insert(neighbor) {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    seen
 }
 
 pub fn update_dep_info(graph: &mut DepGraph) {
@@ -29,26 +254,40 @@ pub fn update_dep_info(graph: &mut DepGraph) {
 }
 
 fn update_node(graph: &mut DepGraph, idx: NodeIndex<u16>) {
-    // Special case for workspace members
-    if graph[idx].dep_info.is_none() {
-        let mut outgoing = graph.neighbors_directed(idx, Direction::Outgoing).detach();
-        while let Some(edge_idx) = outgoing.next_edge(graph) {
-            graph[edge_idx].visited = true;
-        }
-
+    // Special case for workspace members: they have no dep-kind of their
+    // own, so there's nothing to compute.
+    if matches!(&graph[idx], Node::Package(pkg) if pkg.is_workspace_member) {
+        mark_outgoing_visited(graph, idx, None);
         return;
     }
 
     let mut incoming = graph.neighbors_directed(idx, Direction::Incoming).detach();
     let mut node_info: Option<DepInfo> = None;
     while let Some((edge_idx, node_idx)) = incoming.next(graph) {
-        if !graph[edge_idx].visited {
+        let already_visited = matches!(graph[edge_idx], Edge::Dep(info) if info.visited);
+        if !already_visited {
             update_node(graph, node_idx);
         }
 
-        let edge_info = graph[edge_idx];
-        assert!(edge_info.visited);
-
+        // A feature-activation edge carries no dependency kind of its own:
+        // it inherits whatever the owning package settled on. If that
+        // package has no `dep_info` (a workspace member, or a node left
+        // dangling by graph filtering), there's nothing to inherit, so this
+        // edge just doesn't contribute to the fold below.
+        let edge_info = match graph[edge_idx] {
+            Edge::Dep(info) => Some(info),
+            Edge::Feature => match &graph[node_idx] {
+                Node::Package(pkg) => pkg.dep_info,
+                Node::Feature { .. } => None,
+            },
+        };
+        let Some(edge_info) = edge_info else { continue };
+
+        // `combine_incoming` folds the composite path kind of each incoming
+        // edge into `node_info`, rather than collapsing to a single
+        // `DepKind`, so e.g. a crate that's only reachable as a build-dep of
+        // a dev-dep keeps that distinction instead of looking like a plain
+        // dev-dep.
         if let Some(i) = &mut node_info {
             i.is_target_dep &= edge_info.is_target_dep;
             i.kind.combine_incoming(edge_info.kind);
@@ -57,15 +296,35 @@ fn update_node(graph: &mut DepGraph, idx: NodeIndex<u16>) {
         }
     }
 
-    let node_info = node_info.expect("non-workspace members to have at least one incoming edge");
-    graph[idx].dep_info = Some(node_info);
+    let Some(node_info) = node_info else {
+        // No incoming edge contributed any usable dep-kind info (e.g. a
+        // feature node whose owning package has none): leave this node
+        // unclassified, the same as a workspace member.
+        mark_outgoing_visited(graph, idx, None);
+        return;
+    };
 
+    if let Node::Package(pkg) = &mut graph[idx] {
+        pkg.dep_info = Some(node_info);
+    }
+
+    mark_outgoing_visited(graph, idx, Some(node_info));
+}
+
+fn mark_outgoing_visited(graph: &mut DepGraph, idx: NodeIndex<u16>, node_info: Option<DepInfo>) {
     let mut outgoing = graph.neighbors_directed(idx, Direction::Outgoing).detach();
     while let Some(edge_idx) = outgoing.next_edge(graph) {
-        let edge_info = &mut graph[edge_idx];
-        edge_info.visited = true;
-        edge_info.is_target_dep |= node_info.is_target_dep;
-        edge_info.kind.update_outgoing(node_info.kind);
+        if let Edge::Dep(edge_info) = &mut graph[edge_idx] {
+            edge_info.visited = true;
+            if let Some(node_info) = node_info {
+                edge_info.is_target_dep |= node_info.is_target_dep;
+                // Combines the edge's own declared kind with the composite
+                // kind of the path leading up to it, so the edge ends up
+                // tagged with the full "build-of-dev"-style path rather than
+                // just its own immediate kind.
+                edge_info.kind.update_outgoing(node_info.kind);
+            }
+        }
     }
 }
 
@@ -76,23 +335,36 @@ pub fn dedup_transitive_deps(graph: &mut DepGraph) {
     for idx in graph.node_indices() {
         let mut outgoing = graph.neighbors_directed(idx, Direction::Outgoing).detach();
         while let Some((edge_idx, node_idx)) = outgoing.next(graph) {
-            if graph.neighbors_directed(node_idx, Direction::Incoming).count() < 2 {
+            // Feature-activation edges aren't transitive dependency edges.
+            if matches!(graph[edge_idx], Edge::Feature) {
+                continue;
+            }
+
+            let Node::Package(target_pkg) = &graph[node_idx] else {
+                continue;
+            };
+
+            let real_dependents = graph
+                .edges_directed(node_idx, Direction::Incoming)
+                .filter(|e| matches!(e.weight(), Edge::Dep(_)))
+                .count();
+            if real_dependents < 2 {
                 // graph[idx] is the only node that depends on graph[node_idx], do nothing
                 break;
             }
 
-            let node_kind = graph[node_idx].dep_kind();
+            let node_kind = target_pkg.dep_kind();
             let paths: Vec<_> =
                 all_simple_paths::<Vec<_>, _>(&*graph, idx, node_idx, 1, None).collect();
-            if paths.iter().any(|path| path.iter().all(|&i| graph[i].dep_kind() == node_kind)) {
+            if paths.iter().any(|path| {
+                path.iter().all(|&i| graph[i].as_package().map(Package::dep_kind) == Some(node_kind))
+            }) {
                 graph.remove_edge(edge_idx);
             }
         }
     }
 }
 
-// TODO: Clone DepKindInfo to be able to distinguish build-dep of test-dep from just test-dep
-
 struct DepGraphBuilder {
     /// The dependency graph being built.
     graph: DepGraph,
@@ -107,14 +379,21 @@ struct DepGraphBuilder {
     packages: Vec<Option<MetaPackage>>,
     /// The dependency graph obtained from cargo_metadata. To be transformed into graph edges.
     resolve: Resolve,
+
+    /// The local cargo-crev proof database, loaded when `Config::crev` is
+    /// set and a crev setup is actually found. `None` otherwise, in which
+    /// case nodes just get no review status.
+    crev: Option<CrevDb>,
 }
 
 impl DepGraphBuilder {
-    fn new(metadata: Metadata) -> anyhow::Result<Self> {
+    fn new(metadata: Metadata, config: &Config) -> anyhow::Result<Self> {
         let resolve = metadata
             .resolve
             .context("Couldn't obtain dependency graph. Your cargo version may be too old.")?;
 
+        let crev = if config.crev { CrevDb::open()? } else { None };
+
         Ok(Self {
             graph: DepGraph::with_capacity(
                 resolve.nodes.len(),
@@ -126,14 +405,25 @@ impl DepGraphBuilder {
             workspace_members: metadata.workspace_members,
             packages: metadata.packages.into_iter().map(Some).collect(),
             resolve,
+            crev,
         })
     }
 
+    /// Builds a `Package` node, looking up its cargo-crev review status when
+    /// a proof database was loaded. Takes `crev` explicitly, rather than
+    /// `&self`, so it can still be called at sites where a field of `self`
+    /// (e.g. `node_indices`, `packages`) is already mutably borrowed.
+    fn make_package(crev: &Option<CrevDb>, pkg: MetaPackage, is_workspace_member: bool) -> Package {
+        let review_status = crev.as_ref().map(|db| db.status_for(&pkg.name, &pkg.version.to_string()));
+        Package::new(pkg, is_workspace_member, review_status)
+    }
+
     fn add_workspace_members(&mut self) -> anyhow::Result<()> {
         for pkg_id in &self.workspace_members {
             let pkg =
                 pop_package(&mut self.packages, pkg_id).context("package not found in packages")?;
-            let node_idx = self.graph.add_node(Package::new(pkg, true));
+            let node_idx =
+                self.graph.add_node(Node::Package(Self::make_package(&self.crev, pkg, true)));
             self.deps_add_queue.push_back(pkg_id.clone());
             let old_val = self.node_indices.insert(pkg_id.clone(), node_idx);
             assert!(old_val.is_none());
@@ -156,6 +446,31 @@ impl DepGraphBuilder {
                 .find(|n| n.id == pkg_id)
                 .context("package not found in resolve")?;
 
+            // Workspace members get feature nodes too (arguably the most
+            // common case: "why did enabling this feature on my own crate
+            // pull in X?"), even though they have no `dep_info` of their
+            // own: `update_node` treats a feature edge whose owner has no
+            // `dep_info` as contributing nothing, rather than panicking, so
+            // there's nothing unsound about it.
+            let feature_indices = if config.features {
+                self.add_feature_nodes(parent_idx, resolve_node)
+            } else {
+                HashMap::new()
+            };
+
+            // Maps each enabled dependency name to the feature (if any)
+            // that actually turns it on, per the parent's own manifest,
+            // rather than assuming a feature only ever gates the
+            // identically-named optional dependency.
+            let gating = if feature_indices.is_empty() {
+                HashMap::new()
+            } else {
+                let parent_pkg = self.graph[parent_idx]
+                    .as_package()
+                    .expect("add_dependencies only queues package nodes");
+                features_gating(&parent_pkg.meta.features, &resolve_node.features)
+            };
+
             for dep in &resolve_node.deps {
                 if dep.dep_kinds.iter().all(|i| skip_dep(config, i)) {
                     continue;
@@ -166,13 +481,27 @@ impl DepGraphBuilder {
                     HashMapEntry::Occupied(o) => *o.get(),
                     HashMapEntry::Vacant(v) => {
                         let pkg = pop_package(&mut packages, &dep.pkg).unwrap();
-                        let idx = self.graph.add_node(Package::new(pkg, false));
+                        let idx = self
+                            .graph
+                            .add_node(Node::Package(Self::make_package(&self.crev, pkg, false)));
                         self.deps_add_queue.push_back(dep.pkg.clone());
                         v.insert(idx);
                         idx
                     }
                 };
 
+                // If this dependency is gated behind one of the parent's own
+                // features, route the edge through that feature node
+                // instead of straight from the package. Falls back to a
+                // same-named feature for the common "optional dep without
+                // its own `[features]` entry" case.
+                let source_idx = gating
+                    .get(&dep.name)
+                    .and_then(|feature| feature_indices.get(feature))
+                    .or_else(|| feature_indices.get(&dep.name))
+                    .copied()
+                    .unwrap_or(parent_idx);
+
                 for info in &dep.dep_kinds {
                     // We checked whether to skip this dependency fully above, but if there's
                     // multiple dependencies from A to B (e.g. normal dependency with no features,
@@ -180,13 +509,13 @@ impl DepGraphBuilder {
                     // some of the edges.
                     if !skip_dep(config, info) {
                         self.graph.add_edge(
-                            parent_idx,
+                            source_idx,
                             child_idx,
-                            DepInfo {
+                            Edge::Dep(DepInfo {
                                 kind: info.kind.into(),
                                 is_target_dep: info.target.is_some(),
                                 visited: false,
-                            },
+                            }),
                         );
                     }
                 }
@@ -195,6 +524,59 @@ impl DepGraphBuilder {
 
         Ok(())
     }
+
+    /// Adds a `Node::Feature` for every feature `resolve_node` has enabled,
+    /// linked from `parent_idx` with a `Edge::Feature` edge. Returns a map
+    /// from feature name to its node index, so callers can route dependency
+    /// edges gated behind that feature through it.
+    fn add_feature_nodes(
+        &mut self,
+        parent_idx: NodeIndex<u16>,
+        resolve_node: &MetaNode,
+    ) -> HashMap<String, NodeIndex<u16>> {
+        resolve_node
+            .features
+            .iter()
+            .map(|feature| {
+                let idx = self.graph.add_node(Node::Feature {
+                    package: resolve_node.id.clone(),
+                    name: feature.clone(),
+                });
+                self.graph.add_edge(parent_idx, idx, Edge::Feature);
+                (feature.clone(), idx)
+            })
+            .collect()
+    }
+}
+
+/// Maps each dependency name that one of `enabled`'s features actually
+/// turns on, per `features` (`MetaPackage::features`), to that feature: a feature's
+/// requirement list can reference a dependency via the implicit
+/// same-named feature, the explicit `dep:name` syntax, or the
+/// `name/feature`/`name?/feature` dependency-feature syntax. Only the
+/// first gating feature found for a given dependency is kept.
+fn features_gating(
+    features: &std::collections::BTreeMap<String, Vec<String>>,
+    enabled: &[String],
+) -> HashMap<String, String> {
+    let mut gating = HashMap::new();
+    for feature in enabled {
+        let Some(requires) = features.get(feature) else {
+            // No `[features]` entry of its own: either an optional
+            // dependency's implicit same-named feature (handled by the
+            // caller's fallback), or unknown to us.
+            continue;
+        };
+
+        for req in requires {
+            let dep_name = req.strip_prefix("dep:").unwrap_or(req);
+            let dep_name = dep_name.split('/').next().unwrap_or(dep_name);
+            let dep_name = dep_name.trim_end_matches('?');
+            gating.entry(dep_name.to_owned()).or_insert_with(|| feature.clone());
+        }
+    }
+
+    gating
 }
 
 fn pop_package(packages: &mut [Option<MetaPackage>], pkg_id: &PackageId) -> Option<MetaPackage> {
@@ -210,3 +592,361 @@ pub fn skip_dep(config: &Config, info: &cargo_metadata::DepKindInfo) -> bool {
         || (!config.dev_deps && info.kind == MetaDepKind::Development)
         || (!config.target_deps && info.target.is_some())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep_info::DepKind;
+
+    /// A minimal but realistic `cargo_metadata::Package` fixture, good
+    /// enough to exercise the graph-building logic that only looks at
+    /// `name`/`version`/`features`.
+    fn test_package(name: &str, features: serde_json::Value) -> MetaPackage {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": "0.1.0",
+            "id": format!("{name} 0.1.0 (path+file:///tmp/{name})"),
+            "license": null,
+            "license_file": null,
+            "description": null,
+            "source": null,
+            "dependencies": [],
+            "targets": [],
+            "features": features,
+            "manifest_path": format!("/tmp/{name}/Cargo.toml"),
+            "edition": "2021",
+            "authors": [],
+            "metadata": null,
+        }))
+        .expect("minimal cargo_metadata::Package fixture should deserialize")
+    }
+
+    fn test_package_id(name: &str) -> PackageId {
+        test_package(name, serde_json::json!({})).id
+    }
+
+    /// A workspace member's feature node has nothing to inherit a
+    /// `dep_info` from (workspace members never get one of their own), so
+    /// `update_node` must treat that as "this edge contributes nothing"
+    /// rather than `.expect()`-panicking.
+    #[test]
+    fn feature_node_on_workspace_member_does_not_panic() {
+        let mut graph = DepGraph::new();
+        let member = Package::new(test_package("member", serde_json::json!({})), true, None);
+        let member_idx = graph.add_node(Node::Package(member));
+        let feature_idx = graph.add_node(Node::Feature {
+            package: test_package_id("member"),
+            name: "default".to_owned(),
+        });
+        graph.add_edge(member_idx, feature_idx, Edge::Feature);
+
+        update_dep_info(&mut graph);
+
+        let Node::Package(member) = &graph[member_idx] else { unreachable!() };
+        assert!(member.dep_info.is_none());
+    }
+
+    #[test]
+    fn features_gating_resolves_dep_colon_and_slash_syntax() {
+        let mut features = std::collections::BTreeMap::new();
+        features.insert("json".to_owned(), vec!["dep:serde_json".to_owned()]);
+        features.insert("foo".to_owned(), vec!["other/bar".to_owned()]);
+        features.insert("weak".to_owned(), vec!["baz?/qux".to_owned()]);
+
+        let enabled =
+            vec!["json".to_owned(), "foo".to_owned(), "weak".to_owned(), "unknown".to_owned()];
+        let gating = features_gating(&features, &enabled);
+
+        assert_eq!(gating.get("serde_json"), Some(&"json".to_owned()));
+        assert_eq!(gating.get("other"), Some(&"foo".to_owned()));
+        assert_eq!(gating.get("baz"), Some(&"weak".to_owned()));
+        assert_eq!(gating.len(), 3);
+    }
+
+    #[test]
+    fn features_gating_falls_back_to_same_named_optional_dep() {
+        // A `[features]`-less optional dependency has no entry in
+        // `MetaPackage::features` at all; callers fall back to matching by
+        // name, so `features_gating` itself should just have nothing to say
+        // about it.
+        let features = std::collections::BTreeMap::new();
+        let enabled = vec!["serde".to_owned()];
+        assert!(features_gating(&features, &enabled).is_empty());
+    }
+
+    fn dep_edge(kind: DepKind) -> Edge {
+        Edge::Dep(DepInfo { kind, is_target_dep: false, visited: false })
+    }
+
+    fn add_package(graph: &mut DepGraph, name: &str) -> NodeIndex<u16> {
+        graph.add_node(Node::Package(Package::new(
+            test_package(name, serde_json::json!({})),
+            false,
+            None,
+        )))
+    }
+
+    #[test]
+    fn a_cycle_of_normal_deps_is_hard_not_dev_induced() {
+        let mut graph = DepGraph::new();
+        let a = add_package(&mut graph, "a");
+        let b = add_package(&mut graph, "b");
+        let c = add_package(&mut graph, "c");
+        graph.add_edge(a, b, dep_edge(DepKind::NORMAL));
+        graph.add_edge(b, c, dep_edge(DepKind::NORMAL));
+        graph.add_edge(c, a, dep_edge(DepKind::NORMAL));
+
+        let err = check_cycles(&graph).unwrap_err();
+        assert!(err.to_string().contains("don't go through a dev-dependency"));
+    }
+
+    #[test]
+    fn a_cycle_closed_only_by_a_dev_dep_is_reported_as_dev_induced_not_hard() {
+        let mut graph = DepGraph::new();
+        let a = add_package(&mut graph, "a");
+        let b = add_package(&mut graph, "b");
+        let c = add_package(&mut graph, "c");
+        graph.add_edge(a, b, dep_edge(DepKind::NORMAL));
+        graph.add_edge(b, c, dep_edge(DepKind::NORMAL));
+        graph.add_edge(c, a, dep_edge(DepKind::DEV));
+
+        let report = check_cycles(&graph).expect("no hard cycle, just a dev-induced one");
+        assert!(report.hard.is_empty());
+        assert_eq!(report.dev_induced.len(), 1);
+    }
+
+    /// Same cycle as `a_cycle_of_normal_deps_is_hard_not_dev_induced`, but
+    /// also closed by a redundant dev-dependency edge. The cycle must still
+    /// be a hard error, listed exactly once in the error message (in
+    /// `tarjan_scc` order, so either direction around the cycle is accepted).
+    #[test]
+    fn hard_cycle_is_not_also_reported_as_dev_induced_when_redundantly_closed_by_a_dev_dep() {
+        let mut graph = DepGraph::new();
+        let a = add_package(&mut graph, "a");
+        let b = add_package(&mut graph, "b");
+        let c = add_package(&mut graph, "c");
+        graph.add_edge(a, b, dep_edge(DepKind::NORMAL));
+        graph.add_edge(b, c, dep_edge(DepKind::NORMAL));
+        graph.add_edge(c, a, dep_edge(DepKind::NORMAL));
+        // Redundant: the cycle above is already hard without this edge.
+        graph.add_edge(c, a, dep_edge(DepKind::DEV));
+
+        let err = check_cycles(&graph).unwrap_err();
+        let message = err.to_string();
+        // Reported exactly once as hard, and not a second time as "fine".
+        assert_eq!(message.matches("a -> b -> c").count()
+            + message.matches("b -> c -> a").count()
+            + message.matches("c -> a -> b").count()
+            + message.matches("c -> b -> a").count()
+            + message.matches("b -> a -> c").count()
+            + message.matches("a -> c -> b").count(), 1);
+    }
+
+    fn test_metadata_package(name: &str, features: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "version": "0.1.0",
+            "id": format!("{name} 0.1.0 (path+file:///tmp/{name})"),
+            "license": null,
+            "license_file": null,
+            "description": null,
+            "source": null,
+            "dependencies": [],
+            "targets": [],
+            "features": features,
+            "manifest_path": format!("/tmp/{name}/Cargo.toml"),
+            "edition": "2021",
+            "authors": [],
+            "metadata": null,
+        })
+    }
+
+    /// Drives the whole `DepGraphBuilder`/`get_dep_graph` pipeline for a
+    /// workspace whose root crate gates an optional dependency behind a
+    /// feature, and checks the dependency edge really gets routed through
+    /// the `Node::Feature` node rather than straight from the package.
+    #[test]
+    fn feature_gated_dependency_is_routed_through_its_feature_node() {
+        let metadata: Metadata = serde_json::from_value(serde_json::json!({
+            "packages": [
+                test_metadata_package("root", serde_json::json!({"fancy": ["dep:widgets"]})),
+                test_metadata_package("widgets", serde_json::json!({})),
+            ],
+            "workspace_members": ["root 0.1.0 (path+file:///tmp/root)"],
+            "workspace_default_members": [],
+            "resolve": {
+                "nodes": [
+                    {
+                        "id": "root 0.1.0 (path+file:///tmp/root)",
+                        "dependencies": ["widgets 0.1.0 (path+file:///tmp/widgets)"],
+                        "deps": [
+                            {
+                                "name": "widgets",
+                                "pkg": "widgets 0.1.0 (path+file:///tmp/widgets)",
+                                "dep_kinds": [{"kind": "normal", "target": null}],
+                            },
+                        ],
+                        "features": ["fancy"],
+                    },
+                    {
+                        "id": "widgets 0.1.0 (path+file:///tmp/widgets)",
+                        "dependencies": [],
+                        "deps": [],
+                        "features": [],
+                    },
+                ],
+                "root": "root 0.1.0 (path+file:///tmp/root)",
+            },
+            "target_directory": "/tmp/target",
+            "version": 1,
+            "workspace_root": "/tmp",
+            "metadata": null,
+        }))
+        .expect("minimal cargo_metadata::Metadata fixture should deserialize");
+
+        let config = crate::cli::Config { features: true, ..crate::cli::Config::default() };
+        let graph = get_dep_graph(metadata, &config).expect("graph should build");
+
+        let feature_idx = graph
+            .node_indices()
+            .find(|&idx| matches!(&graph[idx], Node::Feature { name, .. } if name == "fancy"))
+            .expect("a `fancy` feature node should exist");
+        let widgets_idx =
+            find_package_node(&graph, "widgets").expect("widgets node should exist");
+        let root_idx = find_package_node(&graph, "root").expect("root node should exist");
+
+        assert!(graph.contains_edge(feature_idx, widgets_idx));
+        assert!(!graph.contains_edge(root_idx, widgets_idx));
+    }
+
+    #[test]
+    fn focus_forward_keeps_ancestors_not_descendants() {
+        let mut graph = DepGraph::new();
+        let root = add_package(&mut graph, "root");
+        let target = add_package(&mut graph, "target");
+        let child = add_package(&mut graph, "child");
+        graph.add_edge(root, target, dep_edge(DepKind::NORMAL));
+        graph.add_edge(target, child, dep_edge(DepKind::NORMAL));
+
+        let focused = focus_dep_graph(&graph, "target", FocusDirection::Forward).unwrap();
+        assert!(find_package_node(&focused, "root").is_some());
+        assert!(find_package_node(&focused, "target").is_some());
+        assert!(find_package_node(&focused, "child").is_none());
+    }
+
+    #[test]
+    fn focus_reverse_keeps_descendants_not_ancestors() {
+        let mut graph = DepGraph::new();
+        let root = add_package(&mut graph, "root");
+        let target = add_package(&mut graph, "target");
+        let child = add_package(&mut graph, "child");
+        graph.add_edge(root, target, dep_edge(DepKind::NORMAL));
+        graph.add_edge(target, child, dep_edge(DepKind::NORMAL));
+
+        let focused = focus_dep_graph(&graph, "target", FocusDirection::Reverse).unwrap();
+        assert!(find_package_node(&focused, "root").is_none());
+        assert!(find_package_node(&focused, "target").is_some());
+        assert!(find_package_node(&focused, "child").is_some());
+    }
+
+    #[test]
+    fn focus_recomputes_dep_info_excluding_edges_dropped_by_filtering() {
+        let mut graph = DepGraph::new();
+        let w = add_package(&mut graph, "w");
+        let q = add_package(&mut graph, "q");
+        let target = add_package(&mut graph, "target");
+        let n = add_package(&mut graph, "n");
+        graph.add_edge(w, target, dep_edge(DepKind::NORMAL));
+        graph.add_edge(target, n, dep_edge(DepKind::NORMAL));
+        // `q` isn't related to `target` at all, but also reaches `n`.
+        graph.add_edge(q, n, dep_edge(DepKind::DEV));
+
+        update_dep_info(&mut graph);
+        let Node::Package(full_n) = &graph[n] else { unreachable!() };
+        assert_eq!(full_n.dep_info.unwrap().kind.label(), "normal+dev");
+
+        // Focusing (reverse) on `target` keeps `target` and its
+        // descendants, i.e. `{target, n}`; `q` isn't a descendant of
+        // `target`, so its dev-dependency edge into `n` doesn't survive.
+        // `n`'s classification must be recomputed from just the surviving
+        // edge, not carry over the stale composite value.
+        let focused = focus_dep_graph(&graph, "target", FocusDirection::Reverse).unwrap();
+        let n_idx = find_package_node(&focused, "n").unwrap();
+        let Node::Package(focused_n) = &focused[n_idx] else { unreachable!() };
+        assert_eq!(focused_n.dep_info.unwrap().kind.label(), "normal");
+    }
+
+    /// Regression test for a hard cycle surviving into `--focus`: before
+    /// `check_cycles` was moved ahead of `focus_dep_graph` in `get_dep_graph`,
+    /// `update_dep_info`'s incoming-edge recursion (via `update_node`) had no
+    /// cycle guard of its own, so a real cycle would recurse forever instead
+    /// of surfacing as the "don't go through a dev-dependency" error.
+    /// `get_dep_graph` must reject this before `--focus` narrows the graph at
+    /// all, not just on the unfocused path.
+    #[test]
+    fn focus_on_a_package_inside_a_hard_cycle_errors_instead_of_recursing_forever() {
+        let metadata: Metadata = serde_json::from_value(serde_json::json!({
+            "packages": [
+                test_metadata_package("a", serde_json::json!({})),
+                test_metadata_package("b", serde_json::json!({})),
+                test_metadata_package("c", serde_json::json!({})),
+            ],
+            "workspace_members": ["a 0.1.0 (path+file:///tmp/a)"],
+            "workspace_default_members": [],
+            "resolve": {
+                "nodes": [
+                    {
+                        "id": "a 0.1.0 (path+file:///tmp/a)",
+                        "dependencies": ["b 0.1.0 (path+file:///tmp/b)"],
+                        "deps": [
+                            {
+                                "name": "b",
+                                "pkg": "b 0.1.0 (path+file:///tmp/b)",
+                                "dep_kinds": [{"kind": "normal", "target": null}],
+                            },
+                        ],
+                        "features": [],
+                    },
+                    {
+                        "id": "b 0.1.0 (path+file:///tmp/b)",
+                        "dependencies": ["c 0.1.0 (path+file:///tmp/c)"],
+                        "deps": [
+                            {
+                                "name": "c",
+                                "pkg": "c 0.1.0 (path+file:///tmp/c)",
+                                "dep_kinds": [{"kind": "normal", "target": null}],
+                            },
+                        ],
+                        "features": [],
+                    },
+                    {
+                        "id": "c 0.1.0 (path+file:///tmp/c)",
+                        "dependencies": ["a 0.1.0 (path+file:///tmp/a)"],
+                        "deps": [
+                            {
+                                "name": "a",
+                                "pkg": "a 0.1.0 (path+file:///tmp/a)",
+                                "dep_kinds": [{"kind": "normal", "target": null}],
+                            },
+                        ],
+                        "features": [],
+                    },
+                ],
+                "root": "a 0.1.0 (path+file:///tmp/a)",
+            },
+            "target_directory": "/tmp/target",
+            "version": 1,
+            "workspace_root": "/tmp",
+            "metadata": null,
+        }))
+        .expect("minimal cargo_metadata::Metadata fixture should deserialize");
+
+        let config = crate::cli::Config {
+            focus: Some(FocusTarget { package: "b".to_owned(), direction: FocusDirection::Forward }),
+            ..crate::cli::Config::default()
+        };
+
+        let err = get_dep_graph(metadata, &config).unwrap_err();
+        assert!(err.to_string().contains("don't go through a dev-dependency"));
+    }
+}
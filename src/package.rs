@@ -0,0 +1,39 @@
+use cargo_metadata::Package as MetaPackage;
+
+use crate::{crev::ReviewStatus, dep_info::DepInfo};
+
+/// A resolved package, as it appears as a node in the dependency graph.
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub meta: MetaPackage,
+    pub is_workspace_member: bool,
+    /// `None` for workspace members, which have no dependency "kind" of
+    /// their own; populated for every other package once `update_dep_info`
+    /// resolves it.
+    pub dep_info: Option<DepInfo>,
+    /// cargo-crev review status for this package's resolved version, when a
+    /// proof database was loaded.
+    pub review_status: Option<ReviewStatus>,
+}
+
+impl Package {
+    pub fn new(
+        meta: MetaPackage,
+        is_workspace_member: bool,
+        review_status: Option<ReviewStatus>,
+    ) -> Self {
+        Self { meta, is_workspace_member, dep_info: None, review_status }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.meta.name
+    }
+
+    /// The dependency-kind path this package was reached by. Workspace
+    /// members have no kind of their own, so this is a `Normal` sentinel
+    /// for them (they can only ever be the start of a path, never in the
+    /// middle of one).
+    pub fn dep_kind(&self) -> crate::dep_info::DepKind {
+        self.dep_info.map_or(crate::dep_info::DepKind::NORMAL, |info| info.kind)
+    }
+}